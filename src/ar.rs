@@ -0,0 +1,97 @@
+//! Iteration over Unix `ar` static-library archives: the container format used for Rust
+//! `.rlib`s and C static libraries. A member's body can be fed straight into `elf64::parse`
+//! or `macho::parse`.
+
+use crate::ParseError;
+
+const MAGIC: &[u8; 8] = b"!<arch>\n";
+const HEADER_SIZE: usize = 60;
+const NAME_FIELD_SIZE: usize = 16;
+const SIZE_FIELD_RANGE: std::ops::Range<usize> = 48..58;
+const TERMINATOR: &[u8; 2] = b"`\n";
+
+pub fn is_archive(data: &[u8]) -> bool {
+    data.get(0..8) == Some(MAGIC)
+}
+
+/// Iterates the members of an `ar` archive, resolving GNU (`//`) and BSD (`#1/<len>`)
+/// extended names and skipping the symbol-index members (`/`, `__.SYMDEF`).
+pub fn parse(data: &[u8]) -> Result<Vec<(String, &[u8])>, ParseError> {
+    if !is_archive(data) {
+        return Err(ParseError::MalformedInput);
+    }
+
+    let mut offset = 8;
+    let mut long_names: Option<&[u8]> = None;
+    let mut members = Vec::new();
+
+    while offset < data.len() {
+        let header = data.get(offset..offset.checked_add(HEADER_SIZE).ok_or(ParseError::MalformedInput)?)
+            .ok_or(ParseError::MalformedInput)?;
+
+        if &header[58..60] != TERMINATOR {
+            return Err(ParseError::MalformedInput);
+        }
+
+        let name_field = trim_name_field(&header[0..NAME_FIELD_SIZE])?;
+        let size: usize = std::str::from_utf8(&header[SIZE_FIELD_RANGE])
+            .ok()
+            .and_then(|s| s.trim_end().parse().ok())
+            .ok_or(ParseError::MalformedInput)?;
+
+        let body_start = offset.checked_add(HEADER_SIZE).ok_or(ParseError::MalformedInput)?;
+        let body_end = body_start.checked_add(size).ok_or(ParseError::MalformedInput)?;
+        let body = data.get(body_start..body_end).ok_or(ParseError::MalformedInput)?;
+
+        // Each member is padded to an even offset.
+        offset = if size % 2 == 1 { body_end.checked_add(1).ok_or(ParseError::MalformedInput)? } else { body_end };
+
+        if name_field == "//" {
+            long_names = Some(body);
+            continue;
+        }
+        if name_field == "/" || name_field == "/SYM64/" || name_field.starts_with("__.SYMDEF") {
+            // Symbol-index members (`/` for 32-bit, GNU's `/SYM64/` for 64-bit archives) carry
+            // no file content a caller could parse.
+            continue;
+        }
+
+        let (name, member_data) = if let Some(table_offset) = name_field.strip_prefix('/') {
+            let table_offset: usize = table_offset.parse().map_err(|_| ParseError::MalformedInput)?;
+            let table = long_names.ok_or(ParseError::MalformedInput)?;
+            (gnu_long_name(table, table_offset)?, body)
+        } else if let Some(len) = name_field.strip_prefix("#1/") {
+            let len: usize = len.parse().map_err(|_| ParseError::MalformedInput)?;
+            let name_bytes = body.get(..len).ok_or(ParseError::MalformedInput)?;
+            let name = std::str::from_utf8(name_bytes)
+                .map_err(|_| ParseError::MalformedInput)?
+                .trim_end_matches('\0')
+                .to_string();
+            (name, &body[len..])
+        } else {
+            (name_field.trim_end_matches('/').to_string(), body)
+        };
+
+        // BSD archives usually store `__.SYMDEF` (or `__.SYMDEF SORTED`) as a `#1/<len>` name.
+        if name.starts_with("__.SYMDEF") {
+            continue;
+        }
+
+        members.push((name, member_data));
+    }
+
+    Ok(members)
+}
+
+fn trim_name_field(field: &[u8]) -> Result<&str, ParseError> {
+    let s = std::str::from_utf8(field).map_err(|_| ParseError::MalformedInput)?;
+    Ok(s.trim_end_matches(' '))
+}
+
+/// Looks up an entry in the GNU extended filename table (the `//` member), where each name is
+/// terminated by `/\n` rather than a NUL byte.
+fn gnu_long_name(table: &[u8], offset: usize) -> Result<String, ParseError> {
+    let slice = table.get(offset..).ok_or(ParseError::MalformedInput)?;
+    let end = slice.windows(2).position(|w| w == b"/\n").ok_or(ParseError::MalformedInput)?;
+    std::str::from_utf8(&slice[..end]).map(|s| s.to_string()).map_err(|_| ParseError::MalformedInput)
+}
@@ -1,8 +1,13 @@
+use std::borrow::Cow;
+use std::cmp::min;
+use std::collections::HashMap;
+use std::convert::TryInto;
 use std::ops::Range;
 
 use crate::ByteOrder;
 use crate::demangle::SymbolData;
 use crate::parser::*;
+use crate::ParseError;
 
 mod elf {
     pub type Address = u64;
@@ -15,6 +20,183 @@ mod elf {
 mod section_type {
     pub const SYMBOL_TABLE: super::elf::Word = 2;
     pub const STRING_TABLE: super::elf::Word = 3;
+    pub const HASH: super::elf::Word = 5;
+    pub const DYNAMIC: super::elf::Word = 6;
+    pub const DYNSYM: super::elf::Word = 11;
+    pub const NOTE: super::elf::Word = 7;
+    pub const GNU_HASH: super::elf::Word = 0x6fff_fff6;
+    pub const GNU_VERDEF: super::elf::Word = 0x6fff_fffd;
+    pub const GNU_VERNEED: super::elf::Word = 0x6fff_fffe;
+    pub const GNU_VERSYM: super::elf::Word = 0x6fff_ffff;
+}
+
+/// Note type of the GNU build-id note (name `"GNU\0"`), whose descriptor is the build-id.
+const NT_GNU_BUILD_ID: u32 = 3;
+
+/// A `DT_NEEDED` entry's tag: the value is an offset into `.dynstr` naming a dependency.
+const DT_NEEDED: i64 = 1;
+/// A `DT_SONAME` entry's tag: the value is an offset into `.dynstr` naming this object itself.
+const DT_SONAME: i64 = 14;
+/// Terminates the `.dynamic` array.
+const DT_NULL: i64 = 0;
+
+/// Number of bits in an `Elf64_Addr`, the word size of the `.gnu.hash` bloom filter.
+const ELFCLASS_BITS: u32 = 64;
+
+const ELF64_SYM_SIZE: usize = 24;
+
+fn read_u16(data: &[u8], offset: usize, byte_order: ByteOrder) -> Option<u16> {
+    let raw: [u8; 2] = data.get(offset..offset.checked_add(2)?)?.try_into().ok()?;
+    Some(match byte_order {
+        ByteOrder::LittleEndian => u16::from_le_bytes(raw),
+        ByteOrder::BigEndian => u16::from_be_bytes(raw),
+    })
+}
+
+fn read_u32(data: &[u8], offset: usize, byte_order: ByteOrder) -> Option<u32> {
+    let raw: [u8; 4] = data.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+    Some(match byte_order {
+        ByteOrder::LittleEndian => u32::from_le_bytes(raw),
+        ByteOrder::BigEndian => u32::from_be_bytes(raw),
+    })
+}
+
+fn read_u64(data: &[u8], offset: usize, byte_order: ByteOrder) -> Option<u64> {
+    let raw: [u8; 8] = data.get(offset..offset.checked_add(8)?)?.try_into().ok()?;
+    Some(match byte_order {
+        ByteOrder::LittleEndian => u64::from_le_bytes(raw),
+        ByteOrder::BigEndian => u64::from_be_bytes(raw),
+    })
+}
+
+/// Reads the name and value of the `index`-th `Elf64_Sym` in `dynsym`, bounds-checked against
+/// the section's own range.
+fn read_dynsym_entry<'d>(
+    data: &'d [u8],
+    dynsym: &Section,
+    strings: &'d [u8],
+    index: usize,
+    byte_order: ByteOrder,
+) -> Option<(Option<&'d str>, u64)> {
+    let range = dynsym.range();
+    let entry_offset = range.start.checked_add(index.checked_mul(ELF64_SYM_SIZE)?)?;
+    if entry_offset.checked_add(ELF64_SYM_SIZE)? > range.end {
+        return None;
+    }
+
+    let name_offset = read_u32(data, entry_offset, byte_order)? as usize;
+    let value = read_u64(data, entry_offset + 8, byte_order)?;
+    Some((parse_null_string(strings, name_offset), value))
+}
+
+fn sysv_hash(name: &[u8]) -> u32 {
+    let mut h: u32 = 0;
+    for &b in name {
+        h = (h << 4).wrapping_add(b as u32);
+        let g = h & 0xf000_0000;
+        if g != 0 {
+            h ^= g >> 24;
+        }
+        h &= !g;
+    }
+    h
+}
+
+fn gnu_hash(name: &[u8]) -> u32 {
+    let mut h: u32 = 5381;
+    for &b in name {
+        h = h.wrapping_mul(33).wrapping_add(b as u32);
+    }
+    h
+}
+
+fn symbol_address_sysv_hash(
+    data: &[u8],
+    byte_order: ByteOrder,
+    hash_section: &Section,
+    dynsym: &Section,
+    strings: &[u8],
+    name: &str,
+) -> Option<u64> {
+    let base = hash_section.range().start;
+    let nbucket = read_u32(data, base, byte_order)? as usize;
+    let nchain = read_u32(data, base.checked_add(4)?, byte_order)? as usize;
+    let buckets_offset = base.checked_add(8)?;
+    let chain_offset = buckets_offset.checked_add(nbucket.checked_mul(4)?)?;
+    if nbucket == 0 {
+        return None;
+    }
+
+    let h = sysv_hash(name.as_bytes()) as usize;
+    let mut y = read_u32(data, buckets_offset.checked_add((h % nbucket).checked_mul(4)?)?, byte_order)? as usize;
+
+    // STN_UNDEF (index 0) terminates the chain. Bound the walk by nchain too: a hostile
+    // `.hash` can point `chain[y]` back at an earlier index to cycle forever otherwise.
+    let mut steps = 0;
+    while y != 0 {
+        if steps >= nchain {
+            return None;
+        }
+        steps += 1;
+
+        let (sym_name, value) = read_dynsym_entry(data, dynsym, strings, y, byte_order)?;
+        if sym_name == Some(name) {
+            return Some(value);
+        }
+        y = read_u32(data, chain_offset.checked_add(y.checked_mul(4)?)?, byte_order)? as usize;
+    }
+    None
+}
+
+fn symbol_address_gnu_hash(
+    data: &[u8],
+    byte_order: ByteOrder,
+    hash_section: &Section,
+    dynsym: &Section,
+    strings: &[u8],
+    name: &str,
+) -> Option<u64> {
+    let base = hash_section.range().start;
+    let nbucket = read_u32(data, base, byte_order)? as usize;
+    let symoffset = read_u32(data, base + 4, byte_order)? as usize;
+    let maskwords = read_u32(data, base + 8, byte_order)? as usize;
+    let shift2 = read_u32(data, base + 12, byte_order)?;
+    if nbucket == 0 || maskwords == 0 || !maskwords.is_power_of_two() {
+        return None;
+    }
+
+    let bloom_offset = base.checked_add(16)?;
+    let buckets_offset = bloom_offset.checked_add(maskwords.checked_mul(8)?)?;
+    let chain_offset = buckets_offset.checked_add(nbucket.checked_mul(4)?)?;
+
+    let h = gnu_hash(name.as_bytes());
+    let bloom_word = read_u64(data, bloom_offset.checked_add(((h / ELFCLASS_BITS) as usize % maskwords).checked_mul(8)?)?, byte_order)?;
+    let bit1 = 1u64 << (h % ELFCLASS_BITS);
+    // shift2 comes straight from the file; shifting a u32 by >= 32 panics in debug builds.
+    let bit2 = 1u64 << (h.checked_shr(shift2).unwrap_or(0) % ELFCLASS_BITS);
+    if bloom_word & bit1 == 0 || bloom_word & bit2 == 0 {
+        return None;
+    }
+
+    let mut sym_index = read_u32(data, buckets_offset.checked_add((h as usize % nbucket).checked_mul(4)?)?, byte_order)? as usize;
+    if sym_index < symoffset {
+        return None;
+    }
+
+    loop {
+        let chain_value = read_u32(data, chain_offset.checked_add((sym_index - symoffset).checked_mul(4)?)?, byte_order)?;
+        if chain_value | 1 == h | 1 {
+            let (sym_name, value) = read_dynsym_entry(data, dynsym, strings, sym_index, byte_order)?;
+            if sym_name == Some(name) {
+                return Some(value);
+            }
+        }
+        // The chain terminates when the low bit of the stored hash is set.
+        if chain_value & 1 != 0 {
+            return None;
+        }
+        sym_index += 1;
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -34,30 +216,40 @@ pub struct Elf64Header {
     pub shstrndx: elf::Half,
 }
 
-fn parse_elf_header(data: &[u8], byte_order: ByteOrder) -> Elf64Header {
-    // TODO: ensure there's enough data
-    let mut s = Stream::new(&data[16..], byte_order);
-    Elf64Header {
-        elf_type: s.read(),
-        machine: s.read(),
-        version: s.read(),
-        entry: s.read(),
-        phoff: s.read(),
-        shoff: s.read(),
-        flags: s.read(),
-        ehsize: s.read(),
-        phentsize: s.read(),
-        phnum: s.read(),
-        shentsize: s.read(),
-        shnum: s.read(),
-        shstrndx: s.read(),
+const RAW_ELF_HEADER_SIZE: usize = std::mem::size_of::<Elf64Header>();
+// Elf64_Shdr: 4 Word fields (name, type, link, info), Address, Offset, and 4 XWord
+// fields (flags, size, addralign, entsize) = 64 bytes.
+const RAW_SECTION_HEADER_SIZE: usize = std::mem::size_of::<elf::Word>() * 4 +
+    std::mem::size_of::<elf::Address>() + std::mem::size_of::<elf::Offset>() +
+    std::mem::size_of::<elf::XWord>() * 4;
+
+fn parse_elf_header(data: &[u8], byte_order: ByteOrder) -> Result<Elf64Header, UnexpectedEof> {
+    let mut s = Stream::new(data.get(16..).ok_or(UnexpectedEof {})?, byte_order);
+    if s.remaining() < RAW_ELF_HEADER_SIZE {
+        return Err(UnexpectedEof {});
     }
+    Ok(Elf64Header {
+        elf_type: s.read()?,
+        machine: s.read()?,
+        version: s.read()?,
+        entry: s.read()?,
+        phoff: s.read()?,
+        shoff: s.read()?,
+        flags: s.read()?,
+        ehsize: s.read()?,
+        phentsize: s.read()?,
+        phnum: s.read()?,
+        shentsize: s.read()?,
+        shnum: s.read()?,
+        shstrndx: s.read()?,
+    })
 }
 #[derive(Debug, Clone, Copy)]
 pub struct Section {
     index: u16,
     name: u32,
     kind: u32,
+    flags: u64,
     link: usize,
     offset: u64,
     size: u64,
@@ -68,44 +260,251 @@ fn parse_elf_sections(
     data: &[u8],
     byte_order: ByteOrder,
     header: &Elf64Header
-) -> Vec<Section> {
-    let count = header.shnum;
-    let section_offset = header.shoff as usize; // TODO: harden
-    let mut s = Stream::new(&data[section_offset..], byte_order);
-    let mut sections = Vec::with_capacity(usize::from(count));
-    for _ in 0..count {
-        // TODO: ensure there's enough data
-        let name: elf::Word = s.read();
-        let kind: elf::Word = s.read();
-        s.skip::<elf::XWord>(); // flags
-        s.skip::<elf::Address>(); // addr
-        let offset = s.read::<elf::Offset>();
-        let size = s.read::<elf::XWord>();
-        let link = s.read::<elf::Word>() as usize;
-        s.skip::<elf::Word>(); // info
-        s.skip::<elf::XWord>(); // addralign
-        let entry_size = s.read::<elf::XWord>();
-
-        // TODO: harden
+) -> Result<Vec<Section>, ParseError> {
+    let count: usize = header.shnum.into();
+    let section_offset: usize = header.shoff.try_into()?;
+    let mut s = Stream::new_at(data, section_offset, byte_order)?;
+    // Don't preallocate space for more than 1024 entries; it's rare in the wild and may OOM
+    let mut sections = Vec::with_capacity(min(count, 1024));
+    while sections.len() < count && s.remaining() >= RAW_SECTION_HEADER_SIZE {
+        let name: elf::Word = s.read()?;
+        let kind: elf::Word = s.read()?;
+        let flags = s.read::<elf::XWord>()?;
+        s.skip::<elf::Address>()?; // addr
+        let offset = s.read::<elf::Offset>()?;
+        let size = s.read::<elf::XWord>()?;
+        let link = s.read::<elf::Word>()? as usize;
+        s.skip::<elf::Word>()?; // info
+        s.skip::<elf::XWord>()?; // addralign
+        let entry_size = s.read::<elf::XWord>()?;
+
+        // TODO: harden?
         let entries = if entry_size == 0 { 0 } else { size / entry_size } as usize;
 
         sections.push(Section {
             index: sections.len() as u16,
             name,
             kind,
+            flags,
             link,
             offset,
             size,
             entries,
         });
     }
-    sections
+    Ok(sections)
 }
 
+/// `SHF_COMPRESSED`: the section data is prefixed with an `Elf64_Chdr` and deflated.
+const SHF_COMPRESSED: u64 = 0x800;
+
+/// `sizeof(Elf64_Chdr)`: `ch_type` (4 bytes) + reserved (4 bytes) + `ch_size` (8 bytes) +
+/// `ch_addralign` (8 bytes).
+const ELF64_CHDR_SIZE: usize = 24;
+
+const ELFCOMPRESS_ZLIB: u32 = 1;
+const ELFCOMPRESS_ZSTD: u32 = 2;
+
+// Reject a `ch_size` larger than this rather than trusting a hostile file to size our
+// allocation, the same way `parse_elf_sections` caps the section count it preallocates for.
+const MAX_DECOMPRESSED_SIZE: u64 = 1024 * 1024 * 1024;
+
 impl Section {
     pub fn range(&self) -> Range<usize> {
         self.offset as usize .. (self.offset as usize + self.size as usize)
     }
+
+    /// Whether this section carries `SHF_COMPRESSED`, i.e. whether [`Elf64::section_data`]
+    /// will inflate it rather than hand back its bytes unchanged.
+    pub fn is_compressed(&self) -> bool {
+        self.flags & SHF_COMPRESSED != 0
+    }
+
+    /// Returns this section's data, transparently inflating it first if it carries
+    /// `SHF_COMPRESSED` (as `.debug_*`/`.zdebug_*` sections commonly do). Sections that aren't
+    /// compressed are returned unchanged; callers that always want the raw on-disk bytes
+    /// should use [`Section::range`] instead.
+    fn decompressed_data<'a>(&self, elf: &Elf64<'a>) -> Result<Cow<'a, [u8]>, ParseError> {
+        let raw = elf.data.get(self.range()).ok_or(ParseError::MalformedInput)?;
+        if !self.is_compressed() {
+            return Ok(Cow::Borrowed(raw));
+        }
+        if raw.len() < ELF64_CHDR_SIZE {
+            return Err(ParseError::MalformedInput);
+        }
+
+        let ch_type = read_u32(raw, 0, elf.byte_order).ok_or(ParseError::MalformedInput)?;
+        let ch_size = read_u64(raw, 8, elf.byte_order).ok_or(ParseError::MalformedInput)?;
+        if ch_size > MAX_DECOMPRESSED_SIZE {
+            return Err(ParseError::MalformedInput);
+        }
+        let payload = &raw[ELF64_CHDR_SIZE..];
+
+        let decompressed = match ch_type {
+            ELFCOMPRESS_ZLIB => decompress_zlib(payload, ch_size as usize)?,
+            ELFCOMPRESS_ZSTD => decompress_zstd(payload, ch_size as usize)?,
+            _ => return Err(ParseError::MalformedInput),
+        };
+        if decompressed.len() as u64 != ch_size {
+            return Err(ParseError::MalformedInput);
+        }
+        Ok(Cow::Owned(decompressed))
+    }
+}
+
+#[cfg(feature = "zlib")]
+fn decompress_zlib(data: &[u8], expected_size: usize) -> Result<Vec<u8>, ParseError> {
+    use std::io::Read;
+    let mut out = Vec::with_capacity(expected_size.min(MAX_DECOMPRESSED_SIZE as usize));
+    // Cap the reader at expected_size + 1: a stream that claims a small ch_size but actually
+    // expands much further would otherwise grow `out` unbounded before the length check below.
+    let limit = (expected_size as u64).checked_add(1).ok_or(ParseError::MalformedInput)?;
+    flate2::read::ZlibDecoder::new(data).take(limit).read_to_end(&mut out)
+        .map_err(|_| ParseError::MalformedInput)?;
+    if out.len() > expected_size {
+        return Err(ParseError::MalformedInput);
+    }
+    Ok(out)
+}
+
+#[cfg(not(feature = "zlib"))]
+fn decompress_zlib(_data: &[u8], _expected_size: usize) -> Result<Vec<u8>, ParseError> {
+    Err(ParseError::MalformedInput)
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_zstd(data: &[u8], expected_size: usize) -> Result<Vec<u8>, ParseError> {
+    use std::io::Read;
+    let mut out = Vec::with_capacity(expected_size.min(MAX_DECOMPRESSED_SIZE as usize));
+    // Cap the reader at expected_size + 1, the same way decompress_zlib does: a stream that
+    // claims a small ch_size but actually expands much further would otherwise grow `out`
+    // unbounded before the length check in decompressed_data runs.
+    let limit = (expected_size as u64).checked_add(1).ok_or(ParseError::MalformedInput)?;
+    let decoder = zstd::stream::Decoder::new(data).map_err(|_| ParseError::MalformedInput)?;
+    decoder.take(limit).read_to_end(&mut out).map_err(|_| ParseError::MalformedInput)?;
+    if out.len() > expected_size {
+        return Err(ParseError::MalformedInput);
+    }
+    Ok(out)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_zstd(_data: &[u8], _expected_size: usize) -> Result<Vec<u8>, ParseError> {
+    Err(ParseError::MalformedInput)
+}
+
+/// One `(d_tag, d_val_or_ptr)` pair from the `.dynamic` array (`Elf64_Dyn`).
+#[derive(Debug, Clone, Copy)]
+pub struct DynamicEntry {
+    pub tag: i64,
+    pub value: u64,
+}
+
+/// One note record from an `SHT_NOTE` section.
+#[derive(Debug, Clone, Copy)]
+pub struct Note<'a> {
+    pub name: &'a [u8],
+    pub ntype: u32,
+    pub desc: &'a [u8],
+}
+
+fn round_up_to_4(n: usize) -> Option<usize> {
+    n.checked_add(3).map(|v| v & !3)
+}
+
+/// Parses a single note starting at `offset` within `data`, returning it along with the
+/// offset of the next note.
+fn parse_note_at(data: &[u8], offset: usize, byte_order: ByteOrder) -> Option<(Note, usize)> {
+    let namesz = read_u32(data, offset, byte_order)? as usize;
+    let descsz = read_u32(data, offset.checked_add(4)?, byte_order)? as usize;
+    let ntype = read_u32(data, offset.checked_add(8)?, byte_order)?;
+
+    let name_start = offset.checked_add(12)?;
+    let name = data.get(name_start..name_start.checked_add(namesz)?)?;
+
+    let desc_start = name_start.checked_add(round_up_to_4(namesz)?)?;
+    let desc = data.get(desc_start..desc_start.checked_add(descsz)?)?;
+
+    let next_offset = desc_start.checked_add(round_up_to_4(descsz)?)?;
+    Some((Note { name, ntype, desc }, next_offset))
+}
+
+fn parse_notes(data: &[u8], byte_order: ByteOrder) -> Vec<Note> {
+    let mut notes = Vec::new();
+    let mut offset = 0;
+    while let Some((note, next_offset)) = parse_note_at(data, offset, byte_order) {
+        notes.push(note);
+        // Guard against a note claiming zero total size, which would spin forever.
+        if next_offset <= offset {
+            break;
+        }
+        offset = next_offset;
+    }
+    notes
+}
+
+/// Mask over a `.gnu.version` (`SHT_GNU_versym`) entry's low 15 bits: the version index. The
+/// top bit (`0x8000`) is the "hidden" flag, which isn't meaningful for symbol lookup.
+const VERSYM_VERSION_MASK: u16 = 0x7fff;
+/// Reserved version indices that don't name an actual version definition: the symbol is local
+/// to the object, or it's present but not given an explicit version.
+const VER_NDX_LOCAL: u16 = 0;
+const VER_NDX_GLOBAL: u16 = 1;
+
+/// Walks the `SHT_GNU_verneed` chain (`.gnu.version_r`), mapping each dependency version's
+/// index (`vna_other`) to its name, read out of `dynstr` at `vna_name`.
+fn parse_verneed(data: &[u8], byte_order: ByteOrder, dynstr: &[u8]) -> Option<HashMap<u16, String>> {
+    let mut versions = HashMap::new();
+    let mut entry_offset = 0usize;
+    loop {
+        let vn_cnt = read_u16(data, entry_offset.checked_add(2)?, byte_order)?;
+        let vn_aux = read_u32(data, entry_offset.checked_add(8)?, byte_order)? as usize;
+        let vn_next = read_u32(data, entry_offset.checked_add(12)?, byte_order)? as usize;
+
+        let mut aux_offset = entry_offset.checked_add(vn_aux)?;
+        for _ in 0..vn_cnt {
+            let vna_other = read_u16(data, aux_offset.checked_add(6)?, byte_order)?;
+            let vna_name = read_u32(data, aux_offset.checked_add(8)?, byte_order)? as usize;
+            let vna_next = read_u32(data, aux_offset.checked_add(12)?, byte_order)? as usize;
+            if let Some(name) = parse_null_string(dynstr, vna_name) {
+                versions.insert(vna_other & VERSYM_VERSION_MASK, name.to_string());
+            }
+            if vna_next == 0 {
+                break;
+            }
+            aux_offset = aux_offset.checked_add(vna_next)?;
+        }
+
+        if vn_next == 0 {
+            break;
+        }
+        entry_offset = entry_offset.checked_add(vn_next)?;
+    }
+    Some(versions)
+}
+
+/// Walks the `SHT_GNU_verdef` chain (`.gnu.version_d`), mapping each definition's own index
+/// (`vd_ndx`) to its first auxiliary name, read out of `dynstr` at `vda_name`.
+fn parse_verdef(data: &[u8], byte_order: ByteOrder, dynstr: &[u8]) -> Option<HashMap<u16, String>> {
+    let mut versions = HashMap::new();
+    let mut entry_offset = 0usize;
+    loop {
+        let vd_ndx = read_u16(data, entry_offset.checked_add(4)?, byte_order)?;
+        let vd_aux = read_u32(data, entry_offset.checked_add(12)?, byte_order)? as usize;
+        let vd_next = read_u32(data, entry_offset.checked_add(16)?, byte_order)? as usize;
+
+        let aux_offset = entry_offset.checked_add(vd_aux)?;
+        let vda_name = read_u32(data, aux_offset, byte_order)? as usize;
+        if let Some(name) = parse_null_string(dynstr, vda_name) {
+            versions.insert(vd_ndx & VERSYM_VERSION_MASK, name.to_string());
+        }
+
+        if vd_next == 0 {
+            break;
+        }
+        entry_offset = entry_offset.checked_add(vd_next)?;
+    }
+    Some(versions)
 }
 
 pub struct Elf64<'a> {
@@ -115,10 +514,10 @@ pub struct Elf64<'a> {
     sections: Vec<Section>,
 }
 
-pub fn parse(data: &[u8], byte_order: ByteOrder) -> Elf64 {
-    let header = parse_elf_header(data, byte_order);
-    let sections = parse_elf_sections(data, byte_order, &header);
-    Elf64 { data, byte_order, header, sections }
+pub fn parse(data: &[u8], byte_order: ByteOrder) -> Result<Elf64, ParseError> {
+    let header = parse_elf_header(data, byte_order)?;
+    let sections = parse_elf_sections(data, byte_order, &header)?;
+    Ok(Elf64 { data, byte_order, header, sections })
 }
 
 impl<'a> Elf64<'a> {
@@ -130,39 +529,208 @@ impl<'a> Elf64<'a> {
         self.sections.clone()
     }
 
+    /// Returns `section`'s data, transparently inflating it if it's `SHF_COMPRESSED`. See
+    /// [`Section::is_compressed`].
+    pub fn section_data(&self, section: &Section) -> Result<Cow<'a, [u8]>, ParseError> {
+        section.decompressed_data(self)
+    }
+
     pub fn section_with_name(&self, name: &str) -> Option<Section> {
         let data = self.data;
-        let section_name_strings_index = self.header.shstrndx; // TODO: validate
         let sections = &self.sections;
-    
-        let section_name_strings = &data[sections[section_name_strings_index as usize].range()];
+        let section_name_strings_index = self.header.shstrndx as usize; // TODO: validate
+        let section_name_strings = data.get(sections.get(section_name_strings_index)?.range())?;
+
         Some(sections.iter().find(|s| {
             parse_null_string(section_name_strings, s.name as usize) == Some(name)
         }).cloned()?)
     }
 
-    pub fn symbols(&self) -> (Vec<SymbolData>, u64) {
-        match self.extract_symbols() {
-            Some(v) => v,
-            None => (Vec::new(), 0),
-        }
+    /// Symbols from `.symtab`, falling back to `.dynsym` for stripped-but-dynamic binaries that
+    /// keep the latter even once the former is gone.
+    pub fn symbols(&self) -> Result<(Vec<SymbolData>, u64), ParseError> {
+        let symbols_section = self.sections.iter().find(|v| v.kind == section_type::SYMBOL_TABLE)
+            .or_else(|| self.sections.iter().find(|v| v.kind == section_type::DYNSYM))
+            .ok_or(ParseError::MalformedInput)?;
+        let (symbols, size) = self.symbols_from(*symbols_section)?;
+        Ok((symbols.into_iter().map(|(_, symbol)| symbol).collect(), size))
     }
 
-    fn extract_symbols(&self) -> Option<(Vec<SymbolData>, u64)> {
+    /// Symbols from `.dynsym` specifically, even when a full `.symtab` is also present.
+    pub fn dynamic_symbols(&self) -> Result<(Vec<SymbolData>, u64), ParseError> {
+        let symbols_section = self.sections.iter().find(|v| v.kind == section_type::DYNSYM)
+            .ok_or(ParseError::MalformedInput)?;
+        let (symbols, size) = self.symbols_from(*symbols_section)?;
+        Ok((symbols.into_iter().map(|(_, symbol)| symbol).collect(), size))
+    }
+
+    /// Like [`Elf64::dynamic_symbols`], but pairs each symbol with its version name (via
+    /// [`Elf64::symbol_version_name`]) resolved from its `.dynsym` index, since that index isn't
+    /// otherwise recoverable once `.dynsym` has been filtered down to named, sized functions.
+    pub fn dynamic_symbols_with_versions(&self) -> Result<(Vec<(SymbolData, Option<String>)>, u64), ParseError> {
+        let symbols_section = self.sections.iter().find(|v| v.kind == section_type::DYNSYM)
+            .ok_or(ParseError::MalformedInput)?;
+        let (symbols, size) = self.symbols_from(*symbols_section)?;
+        let symbols = symbols.into_iter()
+            .map(|(index, symbol)| (symbol, self.symbol_version_name(index)))
+            .collect();
+        Ok((symbols, size))
+    }
+
+    /// Reads symbols out of `symbols_section` (either `.symtab` or `.dynsym`; both share the
+    /// same entry layout and link to a string table the same way), paired with each symbol's
+    /// index into `symbols_section` so that e.g. `.dynsym` entries can still be looked up in
+    /// `.gnu.version` after the zero-size/unnamed/non-function ones have been filtered out.
+    fn symbols_from(&self, symbols_section: Section) -> Result<(Vec<(usize, SymbolData)>, u64), ParseError> {
         let data = self.data;
         let sections = &self.sections;
 
-        let text_section = self.section_with_name(".text")?;
-        let symbols_section = sections.iter().find(|v| v.kind == section_type::SYMBOL_TABLE)?;
-        let linked_section = sections.get(symbols_section.link)?;
+        let text_section = self.section_with_name(".text")
+            .ok_or(ParseError::MalformedInput)?;
+        let linked_section = sections.get(symbols_section.link)
+            .ok_or(ParseError::MalformedInput)?;
         if linked_section.kind != section_type::STRING_TABLE {
+            return Err(ParseError::MalformedInput);
+        }
+
+        let strings = data.get(linked_section.range()).ok_or(ParseError::MalformedInput)?;
+        let s = Stream::new(data.get(symbols_section.range()).ok_or(ParseError::MalformedInput)?, self.byte_order);
+        let symbols = parse_symbols(s, symbols_section.entries, strings, text_section)?;
+        Ok((symbols, text_section.size))
+    }
+
+    /// Looks up a dynamic symbol's address by name via `.hash`/`.gnu.hash`, without scanning
+    /// the whole `.dynsym` table. Falls back to `None` if the binary has neither hash section
+    /// or any offset involved turns out to be malformed.
+    pub fn symbol_address(&self, name: &str) -> Option<u64> {
+        let data = self.data;
+        let sections = &self.sections;
+
+        let dynsym = sections.iter().find(|s| s.kind == section_type::DYNSYM)?;
+        let dynstr = sections.get(dynsym.link)?;
+        if dynstr.kind != section_type::STRING_TABLE {
             return None;
         }
-    
-        let strings = &data[linked_section.range()];
-        let s = Stream::new(&data[symbols_section.range()], self.byte_order);
-        let symbols = parse_symbols(s, symbols_section.entries, strings, text_section);
-        Some((symbols, text_section.size))
+        let strings = data.get(dynstr.range())?;
+
+        if let Some(hash) = sections.iter().find(|s| s.kind == section_type::GNU_HASH) {
+            if let Some(addr) = symbol_address_gnu_hash(data, self.byte_order, hash, dynsym, strings, name) {
+                return Some(addr);
+            }
+        }
+        if let Some(hash) = sections.iter().find(|s| s.kind == section_type::HASH) {
+            return symbol_address_sysv_hash(data, self.byte_order, hash, dynsym, strings, name);
+        }
+        None
+    }
+
+    /// Walks the `.dynamic` section as an array of `Elf64_Dyn` entries, stopping at `DT_NULL`.
+    pub fn dynamic_entries(&self) -> Vec<DynamicEntry> {
+        self.parse_dynamic_entries().unwrap_or_default()
+    }
+
+    fn parse_dynamic_entries(&self) -> Option<Vec<DynamicEntry>> {
+        let section = self.sections.iter().find(|s| s.kind == section_type::DYNAMIC)?;
+        let mut s = Stream::new(self.data.get(section.range())?, self.byte_order);
+
+        // Don't preallocate space for more than 1024 entries; it's rare in the wild and may OOM
+        let mut entries = Vec::with_capacity(std::cmp::min(section.entries, 1024));
+        while s.remaining() >= 16 {
+            let tag = s.read::<u64>().ok()? as i64;
+            let value: u64 = s.read().ok()?;
+            entries.push(DynamicEntry { tag, value });
+            if tag == DT_NULL {
+                break;
+            }
+        }
+        Some(entries)
+    }
+
+    /// The `DT_NEEDED` entries: names of the shared libraries this object depends on.
+    pub fn needed_libraries(&self) -> Vec<&str> {
+        self.dynamic_strings(DT_NEEDED)
+    }
+
+    /// The `DT_SONAME` entry, if any: the name this shared object advertises to its users.
+    pub fn soname(&self) -> Option<&str> {
+        self.dynamic_strings(DT_SONAME).into_iter().next()
+    }
+
+    fn dynamic_strings(&self, tag: i64) -> Vec<&str> {
+        let dynstr = match self.section_with_name(".dynstr") {
+            Some(s) => s,
+            None => return Vec::new(),
+        };
+        let strings = match self.data.get(dynstr.range()) {
+            Some(s) => s,
+            None => return Vec::new(),
+        };
+
+        self.dynamic_entries().iter()
+            .filter(|e| e.tag == tag)
+            .filter_map(|e| parse_null_string(strings, e.value as usize))
+            .collect()
+    }
+
+    /// Iterates the notes from every `SHT_NOTE` section.
+    pub fn notes(&self) -> Vec<Note<'a>> {
+        self.sections.iter()
+            .filter(|s| s.kind == section_type::NOTE)
+            .filter_map(|s| self.data.get(s.range()))
+            .flat_map(|data| parse_notes(data, self.byte_order))
+            .collect()
+    }
+
+    /// The GNU build-id: a stable identifier that lets tooling correlate a binary with its
+    /// debug symbols without parsing the whole symbol table.
+    pub fn build_id(&self) -> Option<&'a [u8]> {
+        let section = self.section_with_name(".note.gnu.build-id")?;
+        let data = self.data.get(section.range())?;
+        parse_notes(data, self.byte_order).into_iter()
+            .find(|n| n.name == b"GNU\0" && n.ntype == NT_GNU_BUILD_ID)
+            .map(|n| n.desc)
+    }
+
+    /// Builds a map from version index to version name, merging `.gnu.version_r`'s dependency
+    /// versions with `.gnu.version_d`'s own ones. Look a `.dynsym` entry's index up via
+    /// [`Elf64::symbol_version_index`] to get the key into this map.
+    pub fn symbol_versions(&self) -> HashMap<u16, String> {
+        let dynstr = match self.section_with_name(".dynstr").and_then(|s| self.data.get(s.range())) {
+            Some(s) => s,
+            None => return HashMap::new(),
+        };
+
+        let mut versions = HashMap::new();
+        if let Some(section) = self.sections.iter().find(|s| s.kind == section_type::GNU_VERNEED) {
+            if let Some(data) = self.data.get(section.range()) {
+                versions.extend(parse_verneed(data, self.byte_order, dynstr).unwrap_or_default());
+            }
+        }
+        if let Some(section) = self.sections.iter().find(|s| s.kind == section_type::GNU_VERDEF) {
+            if let Some(data) = self.data.get(section.range()) {
+                versions.extend(parse_verdef(data, self.byte_order, dynstr).unwrap_or_default());
+            }
+        }
+        versions
+    }
+
+    /// The version index (low 15 bits of the `.gnu.version` entry) for the `dynsym_index`-th
+    /// dynamic symbol. `VER_NDX_LOCAL`/`VER_NDX_GLOBAL` (0/1) mean it's unversioned; any other
+    /// value is a key into [`Elf64::symbol_versions`].
+    pub fn symbol_version_index(&self, dynsym_index: usize) -> Option<u16> {
+        let section = self.sections.iter().find(|s| s.kind == section_type::GNU_VERSYM)?;
+        let data = self.data.get(section.range())?;
+        let raw = read_u16(data, dynsym_index.checked_mul(2)?, self.byte_order)?;
+        Some(raw & VERSYM_VERSION_MASK)
+    }
+
+    /// The version name for the `dynsym_index`-th dynamic symbol (e.g. `"GLIBC_2.14"`), or
+    /// `None` if it's unversioned or the binary carries no version tables.
+    pub fn symbol_version_name(&self, dynsym_index: usize) -> Option<String> {
+        match self.symbol_version_index(dynsym_index)? {
+            VER_NDX_LOCAL | VER_NDX_GLOBAL => None,
+            index => self.symbol_versions().get(&index).cloned(),
+        }
     }
 }
 
@@ -172,16 +740,20 @@ fn parse_symbols(
     count: usize,
     strings: &[u8],
     text_section: Section,
-) -> Vec<SymbolData> {
+) -> Result<Vec<(usize, SymbolData)>, UnexpectedEof> {
     let mut symbols = Vec::with_capacity(count);
+    let mut index = 0;
     while !s.at_end() {
         // Note: the order of fields in 32 and 64 bit ELF is different.
-        let name_offset = s.read::<elf::Word>() as usize;
-        let info: u8 = s.read();
-        s.skip::<u8>(); // other
-        let shndx: elf::Half = s.read();
-        let value: elf::Address = s.read();
-        let size: elf::XWord = s.read();
+        let name_offset = s.read::<elf::Word>()? as usize;
+        let info: u8 = s.read()?;
+        s.skip::<u8>()?; // other
+        let shndx: elf::Half = s.read()?;
+        let value: elf::Address = s.read()?;
+        let size: elf::XWord = s.read()?;
+
+        let entry_index = index;
+        index += 1;
 
         if shndx != text_section.index {
             continue;
@@ -205,13 +777,99 @@ fn parse_symbols(
         }
 
         if let Some(s) = parse_null_string(strings, name_offset) {
-            symbols.push(SymbolData {
+            symbols.push((entry_index, SymbolData {
                 name: crate::demangle::SymbolName::demangle(s),
                 address: value,
                 size,
-            });
+            }));
         }
     }
 
-    symbols
+    Ok(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Known-answer vectors, cross-checked against an independent implementation of each hash.
+    #[test]
+    fn sysv_hash_known_vectors() {
+        assert_eq!(sysv_hash(b""), 0x0);
+        assert_eq!(sysv_hash(b"a"), 0x61);
+        assert_eq!(sysv_hash(b"printf"), 0x0779_05a6);
+        assert_eq!(sysv_hash(b"main"), 0x0007_37fe);
+    }
+
+    #[test]
+    fn gnu_hash_known_vectors() {
+        assert_eq!(gnu_hash(b""), 0x1505);
+        assert_eq!(gnu_hash(b"a"), 0x2b606);
+        assert_eq!(gnu_hash(b"printf"), 0x156b_2bb8);
+        assert_eq!(gnu_hash(b"main"), 0x7c9a_7f6a);
+    }
+
+    fn push_sym(data: &mut Vec<u8>, name_offset: u32, value: u64) {
+        data.extend_from_slice(&name_offset.to_le_bytes());
+        data.extend_from_slice(&[0u8; 4]); // info, other, shndx
+        data.extend_from_slice(&value.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes()); // size
+    }
+
+    fn section(offset: usize, size: usize) -> Section {
+        Section { index: 0, name: 0, kind: 0, flags: 0, link: 0, offset: offset as u64, size: size as u64, entries: 0 }
+    }
+
+    #[test]
+    fn symbol_address_sysv_hash_round_trip() {
+        let mut data = Vec::new();
+        push_sym(&mut data, 0, 0); // STN_UNDEF
+        push_sym(&mut data, 1, 0x1000); // "printf"
+        let dynsym = section(0, data.len());
+
+        let hash_offset = data.len();
+        data.extend_from_slice(&1u32.to_le_bytes()); // nbucket
+        data.extend_from_slice(&2u32.to_le_bytes()); // nchain
+        data.extend_from_slice(&1u32.to_le_bytes()); // bucket[0] -> dynsym index 1
+        data.extend_from_slice(&0u32.to_le_bytes()); // chain[0] (STN_UNDEF's slot, unused)
+        data.extend_from_slice(&0u32.to_le_bytes()); // chain[1], terminates after one step
+        let hash = section(hash_offset, data.len() - hash_offset);
+
+        let strings = b"\0printf\0";
+        let addr = symbol_address_sysv_hash(&data, ByteOrder::LittleEndian, &hash, &dynsym, strings, "printf");
+        assert_eq!(addr, Some(0x1000));
+
+        let missing = symbol_address_sysv_hash(&data, ByteOrder::LittleEndian, &hash, &dynsym, strings, "missing");
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn symbol_address_gnu_hash_round_trip() {
+        let mut data = Vec::new();
+        push_sym(&mut data, 0, 0); // STN_UNDEF
+        push_sym(&mut data, 1, 0x2000); // "printf"
+        let dynsym = section(0, data.len());
+
+        let h = gnu_hash(b"printf");
+        let shift2 = 6u32;
+        let bit1 = 1u64 << (h % ELFCLASS_BITS);
+        let bit2 = 1u64 << (h.checked_shr(shift2).unwrap_or(0) % ELFCLASS_BITS);
+
+        let hash_offset = data.len();
+        data.extend_from_slice(&1u32.to_le_bytes()); // nbucket
+        data.extend_from_slice(&1u32.to_le_bytes()); // symoffset: dynsym index 1 is the first one hashed
+        data.extend_from_slice(&1u32.to_le_bytes()); // maskwords
+        data.extend_from_slice(&shift2.to_le_bytes());
+        data.extend_from_slice(&(bit1 | bit2).to_le_bytes()); // bloom[0]
+        data.extend_from_slice(&1u32.to_le_bytes()); // buckets[0] -> dynsym index 1
+        data.extend_from_slice(&(h | 1).to_le_bytes()); // chain[0], low bit set terminates the chain
+        let hash = section(hash_offset, data.len() - hash_offset);
+
+        let strings = b"\0printf\0";
+        let addr = symbol_address_gnu_hash(&data, ByteOrder::LittleEndian, &hash, &dynsym, strings, "printf");
+        assert_eq!(addr, Some(0x2000));
+
+        let missing = symbol_address_gnu_hash(&data, ByteOrder::LittleEndian, &hash, &dynsym, strings, "missing");
+        assert_eq!(missing, None);
+    }
 }
@@ -27,4 +27,111 @@ pub use crate::error::ParseError;
 pub enum ByteOrder {
     LittleEndian,
     BigEndian,
+}
+
+const ELF_MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+const ELFCLASS32: u8 = 1;
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const ELFDATA2MSB: u8 = 2;
+
+const PE_MAGIC: [u8; 2] = [b'M', b'Z'];
+
+/// The container format of a binary, as recognized from its leading bytes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Elf32 { byte_order: ByteOrder },
+    Elf64 { byte_order: ByteOrder },
+    Macho,
+    MachoFat,
+    PE,
+    Archive,
+    Unknown,
+}
+
+/// Sniffs the container format of `data` from its magic bytes, without fully parsing it.
+pub fn detect_format(data: &[u8]) -> Format {
+    if ar::is_archive(data) {
+        return Format::Archive;
+    }
+
+    if data.get(0..4) == Some(&ELF_MAGIC) {
+        let byte_order = match data.get(5) {
+            Some(&ELFDATA2LSB) => ByteOrder::LittleEndian,
+            Some(&ELFDATA2MSB) => ByteOrder::BigEndian,
+            _ => return Format::Unknown,
+        };
+        return match data.get(4) {
+            Some(&ELFCLASS32) => Format::Elf32 { byte_order },
+            Some(&ELFCLASS64) => Format::Elf64 { byte_order },
+            _ => Format::Unknown,
+        };
+    }
+
+    if macho::is_macho_fat(data) {
+        return Format::MachoFat;
+    }
+
+    if macho::is_macho(data) {
+        return Format::Macho;
+    }
+
+    if data.get(0..2) == Some(&PE_MAGIC) {
+        return Format::PE;
+    }
+
+    Format::Unknown
+}
+
+/// An ELF header, of whichever class the file turned out to be.
+#[derive(Clone, Copy, Debug)]
+pub enum ElfHeader {
+    Elf32(elf32::Elf64Header),
+    Elf64(elf64::Elf64Header),
+}
+
+/// An ELF section, of whichever class the file turned out to be.
+#[derive(Clone, Copy, Debug)]
+pub enum ElfSection {
+    Elf32(elf32::Section),
+    Elf64(elf64::Section),
+}
+
+/// A parsed ELF file of either class, as returned by [`parse_elf`].
+pub enum Elf<'a> {
+    Elf32(elf32::Elf64<'a>),
+    Elf64(elf64::Elf64<'a>),
+}
+
+impl<'a> Elf<'a> {
+    pub fn header(&self) -> ElfHeader {
+        match self {
+            Elf::Elf32(elf) => ElfHeader::Elf32(elf.header()),
+            Elf::Elf64(elf) => ElfHeader::Elf64(elf.header()),
+        }
+    }
+
+    pub fn section_with_name(&self, name: &str) -> Option<ElfSection> {
+        match self {
+            Elf::Elf32(elf) => elf.section_with_name(name).map(ElfSection::Elf32),
+            Elf::Elf64(elf) => elf.section_with_name(name).map(ElfSection::Elf64),
+        }
+    }
+
+    pub fn symbols(&self) -> Result<(Vec<demangle::SymbolData>, u64), ParseError> {
+        match self {
+            Elf::Elf32(elf) => elf.symbols(),
+            Elf::Elf64(elf) => elf.symbols(),
+        }
+    }
+}
+
+/// Reads `e_ident` to pick `elf32::parse` or `elf64::parse` with the right [`ByteOrder`]
+/// automatically, so callers don't need to already know a file's class and endianness.
+pub fn parse_elf(data: &[u8]) -> Result<Elf, ParseError> {
+    match detect_format(data) {
+        Format::Elf32 { byte_order } => Ok(Elf::Elf32(elf32::parse(data, byte_order)?)),
+        Format::Elf64 { byte_order } => Ok(Elf::Elf64(elf64::parse(data, byte_order)?)),
+        _ => Err(ParseError::MalformedInput),
+    }
 }
\ No newline at end of file
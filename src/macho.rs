@@ -8,8 +8,28 @@ use std::ops::Range;
 use std::convert::TryInto;
 
 const LC_SYMTAB: u32 = 0x2;
+const LC_SEGMENT: u32 = 0x1;
 const LC_SEGMENT_64: u32 = 0x19;
 
+const MH_MAGIC: u32 = 0xFEEDFACE;
+const MH_CIGAM: u32 = 0xCEFAEDFE;
+const MH_MAGIC_64: u32 = 0xFEEDFACF;
+const MH_CIGAM_64: u32 = 0xCFFAEDFE;
+
+/// Magic of a fat/universal Mach-O archive. Always big-endian on disk, regardless of the
+/// endianness of the architecture slices it contains.
+pub(crate) const FAT_MAGIC: u32 = 0xCAFEBABE;
+/// Like `FAT_MAGIC`, but the arch table entries carry 64-bit `offset`/`size` fields.
+pub(crate) const FAT_MAGIC_64: u32 = 0xCAFEBABF;
+
+pub(crate) fn is_macho(data: &[u8]) -> bool {
+    parse_magic(data).is_ok()
+}
+
+pub(crate) fn is_macho_fat(data: &[u8]) -> bool {
+    matches!(data.get(0..4), Some(raw) if matches!(u32::from_be_bytes(raw.try_into().unwrap()), FAT_MAGIC | FAT_MAGIC_64))
+}
+
 #[derive(Debug, Clone, Copy)]
 struct Cmd {
     kind: u32,
@@ -23,14 +43,109 @@ pub struct Section <'a> {
     address: u64,
     offset: u32,
     size: u64,
+    reloff: u32,
+    nreloc: u32,
 }
 
-impl Section <'_> {
+impl <'a> Section <'a> {
     pub fn range(&self) -> Result<Range<usize>, ParseError> {
         let start: usize = self.offset.try_into()?;
         let end: usize = start.checked_add(self.size.try_into()?).ok_or(ParseError::MalformedInput)?;
         Ok(start..end)
     }
+
+    /// Decodes this section's relocation entries (`reloff`/`nreloc`). Returns
+    /// `ParseError::MalformedInput` if the table runs past the end of the buffer; the
+    /// iterator itself stops at the end of its data rather than panicking.
+    pub fn relocations(&self, macho: &Macho<'a>) -> Result<RelocationIterator<'a>, ParseError> {
+        let start: usize = self.reloff.try_into()?;
+        let len = (self.nreloc as usize).checked_mul(RELOCATION_INFO_SIZE)
+            .ok_or(ParseError::MalformedInput)?;
+        let end = start.checked_add(len).ok_or(ParseError::MalformedInput)?;
+        let data = macho.data.get(start..end).ok_or(ParseError::MalformedInput)?;
+        Ok(RelocationIterator {
+            stream: Stream::new(data, macho.byte_order),
+            remaining: self.nreloc,
+            byte_order: macho.byte_order,
+        })
+    }
+}
+
+const RELOCATION_INFO_SIZE: usize = 8;
+
+/// A decoded Mach-O relocation entry (`relocation_info`/`scattered_relocation_info`).
+#[derive(Debug, Clone, Copy)]
+pub enum RelocationInfo {
+    Normal {
+        r_address: i32,
+        r_symbolnum: u32,
+        r_pcrel: bool,
+        r_length: u8,
+        r_extern: bool,
+        r_type: u8,
+    },
+    Scattered {
+        r_address: i32,
+        r_type: u8,
+        r_length: u8,
+        r_pcrel: bool,
+        r_value: u32,
+    },
+}
+
+pub struct RelocationIterator<'a> {
+    stream: Stream<'a>,
+    remaining: u32,
+    byte_order: ByteOrder,
+}
+
+impl Iterator for RelocationIterator<'_> {
+    type Item = RelocationInfo;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 || self.stream.remaining() < RELOCATION_INFO_SIZE {
+            return None;
+        }
+
+        let word1: u32 = self.stream.read().ok()?;
+        let item = if word1 & 0x8000_0000 != 0 {
+            let r_value: u32 = self.stream.read().ok()?;
+            RelocationInfo::Scattered {
+                r_address: (word1 & 0x00FF_FFFF) as i32,
+                r_type: ((word1 >> 24) & 0xF) as u8,
+                r_length: ((word1 >> 28) & 0x3) as u8,
+                r_pcrel: (word1 >> 30) & 0x1 != 0,
+                r_value,
+            }
+        } else {
+            let word2: u32 = self.stream.read().ok()?;
+            // Unlike scattered_relocation_info, relocation_info's bitfield isn't declared with
+            // endian-swapped field order, so the C compiler that produced the object packs it
+            // MSB-first on a big-endian target and LSB-first on a little-endian one.
+            if self.byte_order == ByteOrder::BigEndian {
+                RelocationInfo::Normal {
+                    r_address: word1 as i32,
+                    r_symbolnum: (word2 >> 8) & 0x00FF_FFFF,
+                    r_pcrel: (word2 >> 7) & 0x1 != 0,
+                    r_length: ((word2 >> 5) & 0x3) as u8,
+                    r_extern: (word2 >> 4) & 0x1 != 0,
+                    r_type: (word2 & 0xF) as u8,
+                }
+            } else {
+                RelocationInfo::Normal {
+                    r_address: word1 as i32,
+                    r_symbolnum: word2 & 0x00FF_FFFF,
+                    r_pcrel: (word2 >> 24) & 0x1 != 0,
+                    r_length: ((word2 >> 25) & 0x3) as u8,
+                    r_extern: (word2 >> 27) & 0x1 != 0,
+                    r_type: ((word2 >> 28) & 0xF) as u8,
+                }
+            }
+        };
+
+        self.remaining -= 1;
+        Some(item)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -51,10 +166,30 @@ pub struct Macho <'a> {
     data: &'a [u8],
     header: MachoHeader,
     commands: Vec<Cmd>,
-    sections: Vec<Section<'a>>
+    sections: Vec<Section<'a>>,
+    is_64: bool,
+    byte_order: ByteOrder,
+}
+
+/// Reads the magic and returns whether the file is 64-bit and which byte order it uses.
+///
+/// The four real Mach-O magics are `MH_MAGIC`/`MH_MAGIC_64` (same byte order as the magic
+/// constant itself) and their byte-swapped counterparts `MH_CIGAM`/`MH_CIGAM_64`, so the magic
+/// alone is enough to determine both bitness and endianness without guessing a host order first.
+fn parse_magic(data: &[u8]) -> Result<(bool, ByteOrder), ParseError> {
+    let raw: [u8; 4] = data.get(0..4)
+        .ok_or(ParseError::UnexpectedEof)?
+        .try_into().unwrap();
+    match u32::from_le_bytes(raw) {
+        MH_MAGIC => Ok((false, ByteOrder::LittleEndian)),
+        MH_CIGAM => Ok((false, ByteOrder::BigEndian)),
+        MH_MAGIC_64 => Ok((true, ByteOrder::LittleEndian)),
+        MH_CIGAM_64 => Ok((true, ByteOrder::BigEndian)),
+        _ => Err(ParseError::MalformedInput),
+    }
 }
 
-fn parse_macho_header(s: &mut Stream) -> Result<MachoHeader, UnexpectedEof> {
+fn parse_macho_header(s: &mut Stream, is_64: bool) -> Result<MachoHeader, UnexpectedEof> {
     s.skip::<u32>()?; // magic
     let header = MachoHeader {
         cputype: s.read()?,
@@ -64,7 +199,10 @@ fn parse_macho_header(s: &mut Stream) -> Result<MachoHeader, UnexpectedEof> {
         sizeofcmds: s.read()?,
         flags: s.read()?,
     };
-    s.skip::<u32>()?; // reserved
+    // The trailing reserved word only exists in the 64-bit mach_header_64.
+    if is_64 {
+        s.skip::<u32>()?; // reserved
+    }
     Ok(header)
 }
 
@@ -117,8 +255,10 @@ impl MachoCommandsIterator<'_> {
 }
 
 pub fn parse(data: &[u8]) -> Result<Macho, ParseError> {
-    let mut s = Stream::new(&data, ByteOrder::LittleEndian);
-    let header = parse_macho_header(&mut s)?;
+    let (is_64, byte_order) = parse_magic(data)?;
+
+    let mut s = Stream::new(&data, byte_order);
+    let header = parse_macho_header(&mut s, is_64)?;
     let number_of_commands = header.ncmds;
 
     // Don't preallocate space for more than 1024 entries; it's rare in the wild and may OOM
@@ -134,13 +274,14 @@ pub fn parse(data: &[u8]) -> Result<Macho, ParseError> {
 
         // cmd_size is a size of a whole command data,
         // so we have to remove the header size first.
-        s.skip_len(cmd_size as usize - 8)?; // TODO: harden
+        let to_skip = (cmd_size as usize).checked_sub(8).ok_or(ParseError::MalformedInput)?;
+        s.skip_len(to_skip)?;
     }
 
     let mut sections: Vec<Section> = Vec::new();
     for cmd in &commands {
         if cmd.kind == LC_SEGMENT_64 {
-            let mut s = Stream::new_at(data, cmd.offset, ByteOrder::LittleEndian)?;
+            let mut s = Stream::new_at(data, cmd.offset, byte_order)?;
             s.skip_len(16)?; // segname
             s.skip::<u64>()?; // vmaddr
             s.skip::<u64>()?; // vmsize
@@ -158,10 +299,10 @@ pub fn parse(data: &[u8]) -> Result<Macho, ParseError> {
                 let size: u64 = s.read()?;
                 let offset: u32 = s.read()?;
                 s.skip::<u32>()?; // align
-                s.skip::<u32>()?; // reloff
-                s.skip::<u32>()?; // nreloc
+                let reloff: u32 = s.read()?;
+                let nreloc: u32 = s.read()?;
                 s.skip::<u32>()?; // flags
-                s.skip_len(12)?; // padding
+                s.skip_len(12)?; // reserved1, reserved2, reserved3
 
                 if let (Some(segment), Some(section)) = (segment_name, section_name) {
                     sections.push(Section {
@@ -170,6 +311,44 @@ pub fn parse(data: &[u8]) -> Result<Macho, ParseError> {
                         address,
                         offset,
                         size,
+                        reloff,
+                        nreloc,
+                    });
+                }
+            }
+        } else if cmd.kind == LC_SEGMENT {
+            let mut s = Stream::new_at(data, cmd.offset, byte_order)?;
+            s.skip_len(16)?; // segname
+            s.skip::<u32>()?; // vmaddr
+            s.skip::<u32>()?; // vmsize
+            s.skip::<u32>()?; // fileoff
+            s.skip::<u32>()?; // filesize
+            s.skip::<u32>()?; // maxprot
+            s.skip::<u32>()?; // initprot
+            let sections_count: u32 = s.read()?;
+            s.skip::<u32>()?; // flags
+
+            for _ in 0..sections_count {
+                let section_name = parse_null_string(s.read_bytes(16)?, 0);
+                let segment_name = parse_null_string(s.read_bytes(16)?, 0);
+                let address: u32 = s.read()?;
+                let size: u32 = s.read()?;
+                let offset: u32 = s.read()?;
+                s.skip::<u32>()?; // align
+                let reloff: u32 = s.read()?;
+                let nreloc: u32 = s.read()?;
+                s.skip::<u32>()?; // flags
+                s.skip_len(8)?; // reserved1, reserved2 (32-bit sections have no reserved3)
+
+                if let (Some(segment), Some(section)) = (segment_name, section_name) {
+                    sections.push(Section {
+                        segment_name: segment,
+                        section_name: section,
+                        address: address as u64,
+                        offset,
+                        size: size as u64,
+                        reloff,
+                        nreloc,
                     });
                 }
             }
@@ -180,14 +359,89 @@ pub fn parse(data: &[u8]) -> Result<Macho, ParseError> {
         header,
         commands,
         sections,
+        is_64,
+        byte_order,
     })
 }
 
+/// A single architecture slice carved out of a fat/universal Mach-O archive.
+#[derive(Debug, Clone, Copy)]
+pub struct FatArch<'a> {
+    pub cputype: u32,
+    pub cpusubtype: u32,
+    pub data: &'a [u8],
+}
+
+const FAT_HEADER_SIZE: usize = 8;
+const FAT_ARCH_SIZE: usize = 20;
+const FAT_ARCH_64_SIZE: usize = 32;
+
+/// Parses a fat/universal Mach-O archive (`FAT_MAGIC`/`FAT_MAGIC_64`) and returns each
+/// architecture slice it contains. Each slice can be handed to [`parse`] on its own.
+pub fn parse_fat(data: &[u8]) -> Result<Vec<FatArch>, ParseError> {
+    // The fat header and its arch table are always big-endian, regardless of the
+    // endianness of the slices it contains.
+    let mut s = Stream::new(data, ByteOrder::BigEndian);
+    let magic: u32 = s.read()?;
+    let is_64 = match magic {
+        FAT_MAGIC => false,
+        FAT_MAGIC_64 => true,
+        _ => return Err(ParseError::MalformedInput),
+    };
+    let nfat_arch: u32 = s.read()?;
+
+    let arch_size = if is_64 { FAT_ARCH_64_SIZE } else { FAT_ARCH_SIZE };
+    // Don't preallocate space for more than 1024 entries; it's rare in the wild and may OOM
+    let mut archs = Vec::with_capacity(min(nfat_arch, 1024) as usize);
+    let header_size = FAT_HEADER_SIZE + nfat_arch as usize * arch_size;
+    for _ in 0..nfat_arch {
+        let cputype: u32 = s.read()?;
+        let cpusubtype: u32 = s.read()?;
+        let (offset, size) = if is_64 {
+            let offset: u64 = s.read()?;
+            let size: u64 = s.read()?;
+            s.skip::<u32>()?; // align
+            s.skip::<u32>()?; // reserved
+            (offset as usize, size as usize)
+        } else {
+            let offset: u32 = s.read()?;
+            let size: u32 = s.read()?;
+            s.skip::<u32>()?; // align
+            (offset as usize, size as usize)
+        };
+
+        let end = offset.checked_add(size).ok_or(ParseError::MalformedInput)?;
+        // Reject slices that run past the buffer or overlap the fat header itself,
+        // a classic fuzzing crash vector.
+        if offset < header_size || end > data.len() {
+            return Err(ParseError::MalformedInput);
+        }
+
+        archs.push(FatArch {
+            cputype,
+            cpusubtype,
+            data: &data[offset..end],
+        });
+    }
+    Ok(archs)
+}
+
 impl <'a> Macho<'a> {
     pub fn header(&self) -> MachoHeader {
         self.header
     }
 
+    /// Whether this is a 64-bit Mach-O object (`MH_MAGIC_64`/`MH_CIGAM_64`) as opposed to a
+    /// 32-bit one. Downstream bloat tooling needs this to pick the right slice width.
+    pub fn is_64_bit(&self) -> bool {
+        self.is_64
+    }
+
+    /// The byte order detected from the magic.
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
     pub fn sections(&self) -> Vec<Section> {
         self.sections.clone()
     }
@@ -199,8 +453,8 @@ impl <'a> Macho<'a> {
     }
 
     fn commands(&self) -> MachoCommandsIterator {
-        let mut s = Stream::new(&self.data, ByteOrder::LittleEndian);
-        let _ = parse_macho_header(&mut s); // skip the header
+        let mut s = Stream::new(&self.data, self.byte_order);
+        let _ = parse_macho_header(&mut s, self.is_64); // skip the header
         MachoCommandsIterator {
             stream: s,
             number_of_commands: self.header.ncmds,
@@ -213,30 +467,36 @@ impl <'a> Macho<'a> {
         let text_section_index = self.sections.iter().position(|x| {
             x.segment_name == "__TEXT" && x.section_name == "__text"
         });
-        assert!(text_section_index == Some(0), "the __TEXT section must be first");
+        if text_section_index != Some(0) {
+            // We rely on __TEXT,__text being first to index into raw symbol numbers below;
+            // an adversarial input shouldn't be able to trigger a panic on that assumption.
+            return Err(ParseError::MalformedInput);
+        }
         let text_section = self.sections[0];
-        assert_ne!(text_section.size, 0);
-    
+        if text_section.size == 0 {
+            return Err(ParseError::MalformedInput);
+        }
+
         if let Some(cmd) = self.commands.iter().find(|v| v.kind == LC_SYMTAB) {
-            let mut s = Stream::new(&self.data[cmd.offset..], ByteOrder::LittleEndian);
+            let mut s = Stream::new(&self.data[cmd.offset..], self.byte_order);
             let symbols_offset: u32 = s.read()?;
             let number_of_symbols: u32 = s.read()?;
             let strings_offset: u32 = s.read()?;
             let strings_size: u32 = s.read()?;
-    
+
             let strings = {
                 let start = strings_offset as usize;
-                let end = start + strings_size as usize;
-                &self.data[start..end]
+                let end = start.checked_add(strings_size as usize).ok_or(ParseError::MalformedInput)?;
+                self.data.get(start..end).ok_or(ParseError::MalformedInput)?
             };
-    
-            let symbols_data = &self.data[symbols_offset as usize..];
+
+            let symbols_data = self.data.get(symbols_offset as usize..).ok_or(ParseError::MalformedInput)?;
             return Ok((
-                parse_symbols(symbols_data, number_of_symbols, strings, text_section)?,
+                parse_symbols(symbols_data, number_of_symbols, strings, text_section, self.is_64, self.byte_order)?,
                 text_section.size,
             ));
         }
-    
+
         Ok((Vec::new(), 0))
     }
 }
@@ -254,15 +514,18 @@ fn parse_symbols(
     count: u32,
     strings: &[u8],
     text_section: Section,
+    is_64: bool,
+    byte_order: ByteOrder,
 ) -> Result<Vec<SymbolData>, UnexpectedEof> {
     let mut raw_symbols = Vec::with_capacity(count as usize);
-    let mut s = Stream::new(data, ByteOrder::LittleEndian);
+    let mut s = Stream::new(data, byte_order);
     for _ in 0..count {
         let string_index: u32 = s.read()?;
         let kind: u8 = s.read()?;
         let section: u8 = s.read()?;
         s.skip::<u16>()?; // description
-        let value: u64 = s.read()?;
+        // `nlist` (32-bit) stores n_value as a u32, `nlist_64` as a u64.
+        let value: u64 = if is_64 { s.read()? } else { s.read::<u32>()? as u64 };
 
         if value == 0 {
             continue;
@@ -340,3 +603,112 @@ fn parse_symbols(
 
     Ok(symbols)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iter(data: &[u8], byte_order: ByteOrder, count: u32) -> RelocationIterator {
+        RelocationIterator {
+            stream: Stream::new(data, byte_order),
+            remaining: count,
+            byte_order,
+        }
+    }
+
+    #[test]
+    fn normal_relocation_little_endian() {
+        let r_address: i32 = 0x1234;
+        let r_symbolnum: u32 = 0xABCDEF;
+        let word1 = r_address as u32;
+        let word2 = r_symbolnum
+            | (1 << 24) // r_pcrel
+            | (2 << 25) // r_length
+            | (1 << 27) // r_extern
+            | (5 << 28); // r_type
+        let mut data = Vec::new();
+        data.extend_from_slice(&word1.to_le_bytes());
+        data.extend_from_slice(&word2.to_le_bytes());
+
+        let info = iter(&data, ByteOrder::LittleEndian, 1).next().unwrap();
+        match info {
+            RelocationInfo::Normal { r_address: a, r_symbolnum: s, r_pcrel, r_length, r_extern, r_type } => {
+                assert_eq!(a, r_address);
+                assert_eq!(s, r_symbolnum);
+                assert!(r_pcrel);
+                assert_eq!(r_length, 2);
+                assert!(r_extern);
+                assert_eq!(r_type, 5);
+            }
+            RelocationInfo::Scattered { .. } => panic!("expected a Normal relocation"),
+        }
+    }
+
+    #[test]
+    fn normal_relocation_big_endian() {
+        let r_address: i32 = 0x1234;
+        let r_symbolnum: u32 = 0xABCDEF;
+        let word1 = r_address as u32;
+        let word2 = (r_symbolnum << 8)
+            | (1 << 7) // r_pcrel
+            | (2 << 5) // r_length
+            | (1 << 4) // r_extern
+            | 5; // r_type
+        let mut data = Vec::new();
+        data.extend_from_slice(&word1.to_be_bytes());
+        data.extend_from_slice(&word2.to_be_bytes());
+
+        let info = iter(&data, ByteOrder::BigEndian, 1).next().unwrap();
+        match info {
+            RelocationInfo::Normal { r_address: a, r_symbolnum: s, r_pcrel, r_length, r_extern, r_type } => {
+                assert_eq!(a, r_address);
+                assert_eq!(s, r_symbolnum);
+                assert!(r_pcrel);
+                assert_eq!(r_length, 2);
+                assert!(r_extern);
+                assert_eq!(r_type, 5);
+            }
+            RelocationInfo::Scattered { .. } => panic!("expected a Normal relocation"),
+        }
+    }
+
+    #[test]
+    fn scattered_relocation() {
+        let r_address: i32 = 0x00ABCDEF;
+        let r_type: u8 = 0x3;
+        let r_length: u8 = 0x2;
+        let r_value: u32 = 0xDEADBEEF;
+        let word1 = 0x8000_0000u32
+            | (r_address as u32 & 0x00FF_FFFF)
+            | ((r_type as u32) << 24)
+            | ((r_length as u32) << 28)
+            | (1 << 30); // r_pcrel
+        let mut data = Vec::new();
+        data.extend_from_slice(&word1.to_le_bytes());
+        data.extend_from_slice(&r_value.to_le_bytes());
+
+        let info = iter(&data, ByteOrder::LittleEndian, 1).next().unwrap();
+        match info {
+            RelocationInfo::Scattered { r_address: a, r_type: t, r_length: l, r_pcrel, r_value: v } => {
+                assert_eq!(a, r_address);
+                assert_eq!(t, r_type);
+                assert_eq!(l, r_length);
+                assert!(r_pcrel);
+                assert_eq!(v, r_value);
+            }
+            RelocationInfo::Normal { .. } => panic!("expected a Scattered relocation"),
+        }
+    }
+
+    #[test]
+    fn truncated_table_stops_instead_of_panicking() {
+        // Declares 2 entries but only carries enough data for one.
+        let mut data = Vec::new();
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut it = iter(&data, ByteOrder::LittleEndian, 2);
+        assert!(it.next().is_some());
+        assert!(it.next().is_none());
+    }
+}
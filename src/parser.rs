@@ -131,21 +131,19 @@ impl<'a> Stream<'a> {
     }
 
     #[inline]
-    pub fn read<T: RawNumber>(&mut self) -> T {
-        let v = T::parse(self);
+    pub fn read<T: RawNumber>(&mut self) -> Result<T, UnexpectedEof> {
+        let v = T::parse(self).ok_or(UnexpectedEof {})?;
         self.offset += mem::size_of::<T>();
-        v.unwrap() // TODO: harden
-        // I'm leaving this as-is FOR NOW because I'm not done refactoring decoders yet,
-        // and putting unwrap() on every single invocation only to change it later
-        // is entirely useless. I'll revisit this once I've converted all 3 decoders
-        // to return errors instead of panicking.
+        Ok(v)
     }
 
     #[inline]
-    pub fn read_bytes(&mut self, len: usize) -> &'a [u8] {
-        let offset = self.offset;
-        self.offset += len; //TODO: harden
-        &self.data[offset..(offset + len)]
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], UnexpectedEof> {
+        let start = self.offset;
+        let end = start.checked_add(len).ok_or(UnexpectedEof {})?;
+        let bytes = self.data.get(start..end).ok_or(UnexpectedEof {})?;
+        self.offset = end;
+        Ok(bytes)
     }
 
     #[inline]
@@ -1,8 +1,12 @@
 // See https://github.com/m4b/goblin/blob/master/src/pe/symbol.rs for details.
 
+use std::cmp::min;
+use std::convert::TryInto;
+
 use crate::ByteOrder;
 use crate::demangle::SymbolData;
 use crate::parser::*;
+use crate::ParseError;
 
 const PE_POINTER_OFFSET: usize = 0x3c;
 const COFF_SYMBOL_SIZE: usize = 18;
@@ -12,7 +16,10 @@ const IMAGE_SYM_DTYPE_FUNCTION: u16 = 2;
 const SIZEOF_PE_MAGIC: usize = 4;
 const SIZEOF_COFF_HEADER: usize = 20;
 
-#[derive(Debug,Copy, Clone)]
+// Don't preallocate space for more symbols than this; it's rare in the wild and may OOM.
+const MAX_PREALLOC_SYMBOLS: usize = 1024;
+
+#[derive(Debug, Copy, Clone)]
 pub struct PeHeader {
     machine: u16,
     number_of_sections: u16,
@@ -24,40 +31,40 @@ pub struct PeHeader {
 }
 
 fn parse_pe_header(s: &mut Stream) -> Result<PeHeader, UnexpectedEof> {
-    s.skip::<u32>(); // magic
+    s.skip::<u32>()?; // magic
     Ok(PeHeader {
-        machine: s.read(),
-        number_of_sections: s.read(),
-        time_date_stamp: s.read(),
-        pointer_to_symbol_table: s.read(),
-        number_of_symbols: s.read(),
-        size_of_optional_header: s.read(),
-        characteristics: s.read(),
+        machine: s.read()?,
+        number_of_sections: s.read()?,
+        time_date_stamp: s.read()?,
+        pointer_to_symbol_table: s.read()?,
+        number_of_symbols: s.read()?,
+        size_of_optional_header: s.read()?,
+        characteristics: s.read()?,
     })
 }
 
-pub fn parse(data: &[u8]) -> (Vec<SymbolData>, u64) {
-    let mut s = Stream::new_at(data, PE_POINTER_OFFSET, ByteOrder::LittleEndian);
-    let pe_pointer = s.read::<u32>() as usize;
+pub fn parse(data: &[u8]) -> Result<(Vec<SymbolData>, u64), ParseError> {
+    let mut s = Stream::new_at(data, PE_POINTER_OFFSET, ByteOrder::LittleEndian)?;
+    let pe_pointer = s.read::<u32>()? as usize;
 
-    let mut s = Stream::new_at(data, pe_pointer, ByteOrder::LittleEndian);
-    let header = parse_pe_header(&mut s).unwrap(); //TODO: harden
+    let mut s = Stream::new_at(data, pe_pointer, ByteOrder::LittleEndian)?;
+    let header = parse_pe_header(&mut s)?;
 
     let mut text_section_size = 0;
     let mut text_section_index = 0;
     {
-        let sections_offset =
-              pe_pointer
-            + SIZEOF_PE_MAGIC
-            + SIZEOF_COFF_HEADER
-            + header.size_of_optional_header as usize;
+        let sections_offset = pe_pointer
+            .checked_add(SIZEOF_PE_MAGIC)
+            .and_then(|v| v.checked_add(SIZEOF_COFF_HEADER))
+            .and_then(|v| v.checked_add(header.size_of_optional_header as usize))
+            .ok_or(ParseError::MalformedInput)?;
 
-        let mut s = Stream::new_at(data, sections_offset, ByteOrder::LittleEndian);
+        let mut s = Stream::new_at(data, sections_offset, ByteOrder::LittleEndian)?;
         for i in 0..header.number_of_sections {
-            let name = s.read_bytes(8);
-            s.skip_len(8); // virtual_size + virtual_address
-            let size_of_raw_data: u32 = s.read();
-            s.skip_len(20); // other data
+            let name = s.read_bytes(8)?;
+            s.skip_len(8)?; // virtual_size + virtual_address
+            let size_of_raw_data: u32 = s.read()?;
+            s.skip_len(20)?; // other data
 
             let len = name.iter().position(|c| *c == 0).unwrap_or(8);
             if std::str::from_utf8(&name[0..len]) == Ok(".text") {
@@ -68,8 +75,8 @@ pub fn parse(data: &[u8]) -> (Vec<SymbolData>, u64) {
         }
     }
 
-    let number_of_symbols = header.number_of_symbols as usize;
-    let mut symbols = Vec::with_capacity(number_of_symbols);
+    let number_of_symbols: usize = header.number_of_symbols.try_into()?;
+    let mut symbols = Vec::with_capacity(min(number_of_symbols, MAX_PREALLOC_SYMBOLS));
 
     // Add the .text section size, which will be used
     // to calculate the size of the last symbol.
@@ -79,19 +86,20 @@ pub fn parse(data: &[u8]) -> (Vec<SymbolData>, u64) {
         size: 0,
     });
 
-    let mut s = Stream::new_at(data, header.pointer_to_symbol_table as usize, ByteOrder::LittleEndian);
-    let symbols_data = s.read_bytes(number_of_symbols * COFF_SYMBOL_SIZE);
+    let mut s = Stream::new_at(data, header.pointer_to_symbol_table as usize, ByteOrder::LittleEndian)?;
+    let symbols_len = number_of_symbols.checked_mul(COFF_SYMBOL_SIZE).ok_or(ParseError::MalformedInput)?;
+    let symbols_data = s.read_bytes(symbols_len)?;
     let string_table_offset = s.offset();
 
     let mut s = Stream::new(symbols_data, ByteOrder::LittleEndian);
     while !s.at_end() {
-        let name = s.read_bytes(8);
-        let value: u32 = s.read();
-        let section_number: i16 = s.read();
-        let kind: u16 = s.read();
-        let storage_class: u8 = s.read();
-        let number_of_aux_symbols: u8 = s.read();
-        s.skip_len(number_of_aux_symbols as usize * COFF_SYMBOL_SIZE);
+        let name = s.read_bytes(8)?;
+        let value: u32 = s.read()?;
+        let section_number: i16 = s.read()?;
+        let kind: u16 = s.read()?;
+        let storage_class: u8 = s.read()?;
+        let number_of_aux_symbols: u8 = s.read()?;
+        s.skip_len(number_of_aux_symbols as usize * COFF_SYMBOL_SIZE)?;
 
         if (kind >> IMAGE_SYM_DTYPE_SHIFT) != IMAGE_SYM_DTYPE_FUNCTION {
             continue;
@@ -111,7 +119,7 @@ pub fn parse(data: &[u8]) -> (Vec<SymbolData>, u64) {
             std::str::from_utf8(&name[0..len]).ok()
         } else {
             let mut s2 = Stream::new(&name[4..], ByteOrder::LittleEndian);
-            let name_offset: u32 = s2.read();
+            let name_offset: u32 = s2.read()?;
             parse_null_string(data, string_table_offset + name_offset as usize)
         };
 
@@ -141,5 +149,5 @@ pub fn parse(data: &[u8]) -> (Vec<SymbolData>, u64) {
     // Remove the last symbol, which is `.text` section size.
     symbols.pop();
 
-    (symbols, text_section_size as u64)
+    Ok((symbols, text_section_size as u64))
 }